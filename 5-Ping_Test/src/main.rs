@@ -1,24 +1,35 @@
 #![windows_subsystem = "windows"]
 
-use ping;
+use ping_util::{CurrentPing, PingError, RttUnit};
 use sdl2::event::Event;
-use sdl2::image::{InitFlag, LoadTexture};
+use sdl2::image::InitFlag;
 use sdl2::keyboard::Keycode;
+use sdl2::messagebox::{MessageBoxFlag, show_simple_message_box};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
 use sdl2::ttf::Font;
-use std::time::Instant;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const BACKGROUND_PATH: &str = "assets/globe_.png";
 
 fn main() -> Result<(), String> {
+    let target = ping_util::target_from_args().map_err(|e| {
+        let _ = show_simple_message_box(MessageBoxFlag::ERROR, "Ping Test", &e, None);
+        e
+    })?;
+
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
-    let ttf_context = sdl2::ttf::init().unwrap();
-    let font_path = "assets/Roboto-Medium.ttf";
-    let font = ttf_context.load_font(font_path, 32)?;
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let (font, font_warning) = sdl_assets::load_font(&ttf_context, 32)?;
+    let (small_font, _) = sdl_assets::load_font(&ttf_context, 18)?;
 
     let window = video_subsystem
-        .window("Ping Test", 500, 500)
+        .window(&format!("Ping Test - {}", target), 500, 500)
         .position_centered()
         .resizable()
         .build()
@@ -29,7 +40,30 @@ fn main() -> Result<(), String> {
 
     let _image_context = sdl2::image::init(InitFlag::PNG)?;
     let texture_creator = canvas.texture_creator();
-    let texture = texture_creator.load_texture("assets/globe_.png")?;
+    let background = sdl_assets::load_background(&texture_creator, BACKGROUND_PATH);
+
+    let warnings: Vec<String> = font_warning
+        .into_iter()
+        .chain(background.as_ref().err().cloned())
+        .collect();
+    if !warnings.is_empty() {
+        let _ = show_simple_message_box(
+            MessageBoxFlag::WARNING,
+            "Ping Test",
+            &warnings.join("\n"),
+            canvas.window(),
+        );
+    }
+    let background = background.ok();
+
+    let current_ping = Arc::new(Mutex::new(CurrentPing::Waiting));
+
+    {
+        let current_clone = Arc::clone(&current_ping);
+        thread::spawn(move || ping_thread(target, current_clone));
+    }
+
+    let mut unit = RttUnit::default();
 
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -39,36 +73,64 @@ fn main() -> Result<(), String> {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::U),
+                    ..
+                } => unit = unit.next(),
                 _ => {}
             }
         }
 
+        canvas.set_draw_color(Color::RGB(5, 16, 28));
         canvas.clear();
-        canvas.copy(&texture, None, None)?;
-        get_ping(&mut canvas, &texture_creator, &font);
+        if let Some(texture) = &background {
+            canvas.copy(texture, None, None)?;
+        }
+        draw_ping(&mut canvas, &texture_creator, &font, &current_ping, unit);
+        draw_unit_hint(&mut canvas, &texture_creator, &small_font, unit);
         canvas.present();
+
+        thread::sleep(Duration::from_millis(16));
     }
 
     Ok(())
 }
 
-fn get_ping(
+fn ping_thread(target: IpAddr, current_ping: Arc<Mutex<CurrentPing>>) {
+    loop {
+        let ping = match ping_util::ping_once(
+            target,
+            ping_util::DEFAULT_TIMEOUT,
+            ping_util::DEFAULT_TTL,
+        ) {
+            Ok(rtt) => CurrentPing::Rtt(rtt),
+            Err(PingError::PermissionDenied) => {
+                *current_ping.lock().unwrap() = CurrentPing::PermissionDenied;
+                return;
+            }
+            Err(e) => CurrentPing::Failed(e.to_string()),
+        };
+
+        if let Ok(mut current) = current_ping.try_lock() {
+            *current = ping;
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn draw_ping(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
     font: &Font,
+    current_ping: &Arc<Mutex<CurrentPing>>,
+    unit: RttUnit,
 ) {
-    let target_ip = "8.8.8.8".parse().unwrap();
-    let mut p = ping::new(target_ip);
-    p.timeout(std::time::Duration::from_secs(2)).ttl(128);
-
-    let start = Instant::now();
-
-    let text = match p.send() {
-        Ok(_) => {
-            let rtt = (start.elapsed().as_secs_f64() * 1000.0) as u64;
-            format!("Ping: {} ms", rtt)
-        }
-        Err(e) => format!("Ping failed: {}", e),
+    let text = match current_ping.lock().unwrap().clone() {
+        CurrentPing::Waiting => "Ping: ...".to_string(),
+        CurrentPing::Rtt(rtt) => format!("Ping: {}", unit.format(rtt)),
+        CurrentPing::Failed(message) => format!("Ping failed: {}", message),
+        CurrentPing::PermissionDenied => PingError::PermissionDenied.to_string(),
     };
 
     let surface = font
@@ -87,3 +149,24 @@ fn get_ping(
         .copy(&text_texture, None, Some(Rect::new(x, y, width, height)))
         .unwrap();
 }
+
+fn draw_unit_hint(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    font: &Font,
+    unit: RttUnit,
+) {
+    let text = format!("[U] units: {}", unit.label());
+    let surface = font
+        .render(&text)
+        .blended(Color::RGB(150, 150, 150))
+        .unwrap();
+    let text_texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .unwrap();
+    let TextureQuery { width, height, .. } = text_texture.query();
+
+    canvas
+        .copy(&text_texture, None, Some(Rect::new(10, 10, width, height)))
+        .unwrap();
+}
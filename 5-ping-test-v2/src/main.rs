@@ -1,26 +1,40 @@
 #![windows_subsystem = "windows"]
 
-use ping;
+use ping_util::{CurrentPing, PingError, RttUnit};
 use sdl2::event::Event;
-use sdl2::image::{InitFlag, LoadTexture};
+use sdl2::image::InitFlag;
 use sdl2::keyboard::Keycode;
+use sdl2::messagebox::{MessageBoxFlag, show_simple_message_box};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
+use sdl2::ttf::Font;
 use std::collections::VecDeque;
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+
+const HISTORY_LEN: usize = 5;
+const CHART_LEN: usize = 60;
+const HISTORY_TOP: i32 = 250;
+const HISTORY_SPACING: i32 = 5;
+const BACKGROUND_PATH: &str = "assets/globe.png";
 
 fn main() -> Result<(), String> {
+    let target = ping_util::target_from_args().map_err(|e| {
+        let _ = show_simple_message_box(MessageBoxFlag::ERROR, "Ping Test", &e, None);
+        e
+    })?;
+
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
-    let ttf_context = sdl2::ttf::init().unwrap();
-    let font = ttf_context.load_font("assets/Roboto-Medium.ttf", 32)?;
-    let small_font = ttf_context.load_font("assets/Roboto-Medium.ttf", 24)?;
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let (font, font_warning) = sdl_assets::load_font(&ttf_context, 32)?;
+    let (small_font, _) = sdl_assets::load_font(&ttf_context, 24)?;
 
     let window = video_subsystem
-        .window("Ping Test", 600, 600)
+        .window(&format!("Ping Test - {}", target), 600, 600)
         .resizable()
         .position_centered()
         .build()
@@ -31,35 +45,65 @@ fn main() -> Result<(), String> {
 
     let _image_context = sdl2::image::init(InitFlag::PNG)?;
     let texture_creator = canvas.texture_creator();
-    let texture = texture_creator.load_texture("assets/globe.png")?;
+    let background = sdl_assets::load_background(&texture_creator, BACKGROUND_PATH);
+
+    let warnings: Vec<String> = font_warning
+        .into_iter()
+        .chain(background.as_ref().err().cloned())
+        .collect();
+    if !warnings.is_empty() {
+        let _ = show_simple_message_box(
+            MessageBoxFlag::WARNING,
+            "Ping Test",
+            &warnings.join("\n"),
+            canvas.window(),
+        );
+    }
+    let background = background.ok();
 
-    let current_ping = Arc::new(Mutex::new(String::from("Ping: ...")));
-    let rtt_history = Arc::new(Mutex::new(VecDeque::with_capacity(5)));
+    let current_ping = Arc::new(Mutex::new(CurrentPing::Waiting));
+    let rtt_history = Arc::new(Mutex::new(VecDeque::with_capacity(CHART_LEN)));
 
     {
         let current_clone = Arc::clone(&current_ping);
         let hist_clone = Arc::clone(&rtt_history);
-        thread::spawn(move || ping_thread(current_clone, hist_clone));
+        thread::spawn(move || ping_thread(target, current_clone, hist_clone));
     }
 
+    let chart_top = HISTORY_TOP + HISTORY_LEN as i32 * (small_font.height() + HISTORY_SPACING);
+
+    let mut unit = RttUnit::default();
+
     'running: loop {
         for event in event_pump.poll_iter() {
-            if let Event::Quit { .. }
-            | Event::KeyDown {
-                keycode: Some(Keycode::Escape),
-                ..
-            } = event
-            {
-                break 'running;
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::U),
+                    ..
+                } => unit = unit.next(),
+                _ => {}
             }
         }
 
         canvas.set_draw_color(Color::RGB(5, 16, 28));
         canvas.clear();
-        canvas.copy(&texture, None, None)?;
+        if let Some(texture) = &background {
+            canvas.copy(texture, None, None)?;
+        }
+
+        // Copy the samples out so the ping thread never waits on a frame being drawn.
+        let history: Vec<Option<Duration>> = rtt_history.lock().unwrap().iter().copied().collect();
 
-        draw_current_ping(&mut canvas, &texture_creator, &font, &current_ping);
-        draw_ping_history(&mut canvas, &texture_creator, &small_font, &rtt_history);
+        draw_current_ping(&mut canvas, &texture_creator, &font, &current_ping, unit);
+        draw_unit_hint(&mut canvas, &texture_creator, &small_font, unit);
+        draw_ping_summary(&mut canvas, &texture_creator, &small_font, &history, unit);
+        draw_ping_history(&mut canvas, &texture_creator, &small_font, &history, unit);
+        draw_ping_chart(&mut canvas, &history, chart_top);
 
         canvas.present();
 
@@ -69,34 +113,34 @@ fn main() -> Result<(), String> {
     Ok(())
 }
 
-fn ping_thread(current_ping: Arc<Mutex<String>>, rtt_history: Arc<Mutex<VecDeque<String>>>) {
-    let target_ip = "8.8.8.8".parse().unwrap();
-    let mut p = ping::new(target_ip);
-    p.timeout(Duration::from_secs(1)).ttl(128);
-
+fn ping_thread(
+    target: IpAddr,
+    current_ping: Arc<Mutex<CurrentPing>>,
+    rtt_history: Arc<Mutex<VecDeque<Option<Duration>>>>,
+) {
     loop {
-        let start = Instant::now();
-        let rtt: Option<u64> = match p.send() {
-            Ok(_) => Some((start.elapsed().as_secs_f64() * 1000.0) as u64),
-            Err(_) => None,
+        let (rtt, ping) = match ping_util::ping_once(
+            target,
+            ping_util::DEFAULT_TIMEOUT,
+            ping_util::DEFAULT_TTL,
+        ) {
+            Ok(d) => (Some(d), CurrentPing::Rtt(d)),
+            Err(PingError::PermissionDenied) => {
+                *current_ping.lock().unwrap() = CurrentPing::PermissionDenied;
+                return;
+            }
+            Err(e) => (None, CurrentPing::Failed(e.to_string())),
         };
 
-        if let Ok(mut hist) = rtt_history.try_lock() {
-            if hist.len() >= 5 {
+        {
+            let mut hist = rtt_history.lock().unwrap();
+            if hist.len() >= CHART_LEN {
                 hist.pop_front();
             }
-            hist.push_back(match rtt {
-                Some(ms) => format!("{} ms", ms),
-                None => "Ping failed".to_string(),
-            });
+            hist.push_back(rtt);
         }
 
-        if let Ok(mut current) = current_ping.try_lock() {
-            *current = match rtt {
-                Some(ms) => format!("Current Ping: {} ms", ms),
-                None => "Ping failed".to_string(),
-            };
-        }
+        *current_ping.lock().unwrap() = ping;
 
         thread::sleep(Duration::from_secs(1));
     }
@@ -105,23 +149,25 @@ fn ping_thread(current_ping: Arc<Mutex<String>>, rtt_history: Arc<Mutex<VecDeque
 fn draw_current_ping(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-    font: &sdl2::ttf::Font,
-    current_ping: &Arc<Mutex<String>>,
+    font: &Font,
+    current_ping: &Arc<Mutex<CurrentPing>>,
+    unit: RttUnit,
 ) {
-    let text = current_ping.lock().unwrap().clone();
+    let current = current_ping.lock().unwrap().clone();
 
-    let rtt_ms: u64 = text
-        .trim_start_matches("Current Ping: ")
-        .trim_end_matches(" ms")
-        .parse()
-        .unwrap_or(9999);
-
-    let color = if rtt_ms < 100 {
-        Color::RGB(0, 255, 0)
-    } else if rtt_ms < 150 {
-        Color::RGB(255, 255, 0)
-    } else {
-        Color::RGB(255, 0, 0)
+    let (text, color) = match current {
+        CurrentPing::Waiting => ("Ping: ...".to_string(), Color::RGB(255, 255, 255)),
+        CurrentPing::Rtt(rtt) => (
+            format!("Current Ping: {}", unit.format(rtt)),
+            rtt_color(rtt),
+        ),
+        CurrentPing::Failed(message) => {
+            (format!("Ping failed: {}", message), Color::RGB(255, 0, 0))
+        }
+        CurrentPing::PermissionDenied => (
+            PingError::PermissionDenied.to_string(),
+            Color::RGB(255, 0, 0),
+        ),
     };
 
     let surface = font.render(&text).blended(color).unwrap();
@@ -141,34 +187,20 @@ fn draw_current_ping(
 fn draw_ping_history(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-    font: &sdl2::ttf::Font,
-    rtt_history: &Arc<Mutex<VecDeque<String>>>,
+    font: &Font,
+    history: &[Option<Duration>],
+    unit: RttUnit,
 ) {
-    let history = rtt_history.lock().unwrap();
     let (window_width, _) = canvas.output_size().unwrap();
 
-    let mut y = 250;
-    for text in history.iter().rev() {
-        let color = if text.contains("failed") {
-            Color::RGB(255, 0, 0)
-        } else {
-            let ms_value: u64 = text
-                .split_whitespace()
-                .next()
-                .unwrap_or("9999")
-                .parse()
-                .unwrap_or(9999);
-
-            if ms_value < 100 {
-                Color::RGB(0, 255, 0)
-            } else if ms_value < 150 {
-                Color::RGB(255, 255, 0)
-            } else {
-                Color::RGB(255, 0, 0)
-            }
+    let mut y = HISTORY_TOP;
+    for rtt in history.iter().rev().take(HISTORY_LEN) {
+        let (text, color) = match rtt {
+            Some(rtt) => (unit.format(*rtt), rtt_color(*rtt)),
+            None => ("Ping failed".to_string(), Color::RGB(255, 0, 0)),
         };
 
-        let surface = font.render(text).blended(color).unwrap();
+        let surface = font.render(&text).blended(color).unwrap();
         let text_texture = texture_creator
             .create_texture_from_surface(&surface)
             .unwrap();
@@ -178,6 +210,182 @@ fn draw_ping_history(
             .copy(&text_texture, None, Some(Rect::new(x, y, width, height)))
             .unwrap();
 
-        y += height as i32 + 5;
+        y += height as i32 + HISTORY_SPACING;
+    }
+}
+
+fn draw_ping_summary(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    font: &Font,
+    history: &[Option<Duration>],
+    unit: RttUnit,
+) {
+    if history.is_empty() {
+        return;
+    }
+
+    let recent = &history[history.len().saturating_sub(HISTORY_LEN)..];
+    let (average, loss) = history_stats(recent);
+    let text = match average {
+        Some(rtt) => format!("Avg: {} | Loss: {:.0}%", unit.format(rtt), loss),
+        None => format!("Avg: -- | Loss: {:.0}%", loss),
+    };
+    let color = if loss > 0.0 {
+        Color::RGB(255, 0, 0)
+    } else {
+        Color::RGB(255, 255, 255)
+    };
+
+    let surface = font.render(&text).blended(color).unwrap();
+    let text_texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .unwrap();
+    let TextureQuery { width, height, .. } = text_texture.query();
+    let (window_width, _) = canvas.output_size().unwrap();
+    let x = window_width as i32 / 2 - width as i32 / 2;
+    let y = 180;
+
+    canvas
+        .copy(&text_texture, None, Some(Rect::new(x, y, width, height)))
+        .unwrap();
+}
+
+fn draw_ping_chart(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    history: &[Option<Duration>],
+    chart_top: i32,
+) {
+    let (window_width, window_height) = canvas.output_size().unwrap();
+
+    // Always start below the text history; on windows too short for that the
+    // chart is skipped rather than drawn over the history rows.
+    let left = 40;
+    let top = chart_top;
+    let right = window_width as i32 - 40;
+    let bottom = window_height as i32 - 20;
+    if right - left < 20 || bottom - top < 20 {
+        return;
+    }
+
+    canvas.set_draw_color(Color::RGB(80, 80, 80));
+    canvas
+        .draw_rect(Rect::new(
+            left,
+            top,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        ))
+        .unwrap();
+
+    let max_val = history
+        .iter()
+        .flatten()
+        .map(|rtt| rtt.as_secs_f32() * 1000.0)
+        .fold(100.0, f32::max);
+    let step = (right - left) as f32 / (CHART_LEN - 1) as f32;
+    let offset = CHART_LEN - history.len();
+
+    let points: Vec<Option<(i32, i32, Duration)>> = history
+        .iter()
+        .enumerate()
+        .map(|(i, rtt)| {
+            rtt.map(|rtt| {
+                let ms = rtt.as_secs_f32() * 1000.0;
+                let x = left + ((offset + i) as f32 * step) as i32;
+                let y = bottom - (ms / max_val * (bottom - top) as f32) as i32;
+                (x, y, rtt)
+            })
+        })
+        .collect();
+
+    for pair in points.windows(2) {
+        if let [Some((x1, y1, _)), Some((x2, y2, rtt))] = pair {
+            canvas.set_draw_color(rtt_color(*rtt));
+            canvas.draw_line((*x1, *y1), (*x2, *y2)).unwrap();
+        }
+    }
+
+    for (x, y, rtt) in points.iter().flatten() {
+        canvas.set_draw_color(rtt_color(*rtt));
+        canvas.fill_rect(Rect::new(x - 2, y - 2, 4, 4)).unwrap();
+    }
+}
+
+fn history_stats(history: &[Option<Duration>]) -> (Option<Duration>, f64) {
+    let replies: Vec<Duration> = history.iter().flatten().copied().collect();
+
+    let average = if replies.is_empty() {
+        None
+    } else {
+        Some(replies.iter().sum::<Duration>() / replies.len() as u32)
+    };
+    let loss = if history.is_empty() {
+        0.0
+    } else {
+        (history.len() - replies.len()) as f64 * 100.0 / history.len() as f64
+    };
+
+    (average, loss)
+}
+
+fn rtt_color(rtt: Duration) -> Color {
+    let rtt_ms = rtt.as_millis();
+    if rtt_ms < 100 {
+        Color::RGB(0, 255, 0)
+    } else if rtt_ms < 150 {
+        Color::RGB(255, 255, 0)
+    } else {
+        Color::RGB(255, 0, 0)
+    }
+}
+
+fn draw_unit_hint(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    font: &Font,
+    unit: RttUnit,
+) {
+    let text = format!("[U] units: {}", unit.label());
+    let surface = font
+        .render(&text)
+        .blended(Color::RGB(150, 150, 150))
+        .unwrap();
+    let text_texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .unwrap();
+    let TextureQuery { width, height, .. } = text_texture.query();
+
+    canvas
+        .copy(&text_texture, None, Some(Rect::new(10, 10, width, height)))
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_stats_empty() {
+        assert_eq!(history_stats(&[]), (None, 0.0));
+    }
+
+    #[test]
+    fn history_stats_all_timeouts() {
+        assert_eq!(history_stats(&[None, None, None]), (None, 100.0));
+    }
+
+    #[test]
+    fn history_stats_averages_replies_only() {
+        let history = [
+            Some(Duration::from_millis(10)),
+            None,
+            Some(Duration::from_millis(30)),
+            None,
+        ];
+        assert_eq!(
+            history_stats(&history),
+            (Some(Duration::from_millis(20)), 50.0)
+        );
     }
 }
@@ -1,26 +1,23 @@
-use ping;
-use std::time::Instant;
+use std::net::IpAddr;
 
 fn main() {
+    let target = match ping_util::target_from_args() {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     loop {
-        println!("{}", get_ping());
+        println!("{}", get_ping(target));
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 }
 
-fn get_ping() -> String {
-    let target_ip = "8.8.8.8".parse::<std::net::IpAddr>().expect("invalid IP");
-
-    let mut p = ping::new(target_ip);
-    p.timeout(std::time::Duration::from_secs(2)).ttl(128);
-
-    let start = Instant::now();
-
-    match p.send() {
-        Ok(_) => {
-            let rtt_ms = start.elapsed().as_millis();
-            format!("{}", rtt_ms)
-        }
+fn get_ping(target: IpAddr) -> String {
+    match ping_util::ping_once(target, ping_util::DEFAULT_TIMEOUT, ping_util::DEFAULT_TTL) {
+        Ok(rtt) => format!("{}", rtt.as_millis()),
         Err(e) => format!("Ping failed: {}", e),
     }
 }
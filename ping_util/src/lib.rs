@@ -0,0 +1,249 @@
+use std::error::Error as _;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TARGET: IpAddr = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+pub const DEFAULT_TTL: u8 = 128;
+
+#[derive(Debug)]
+pub enum PingError {
+    /// The OS refused to open the raw ICMP socket.
+    PermissionDenied,
+    Timeout,
+    Send(ping::Error),
+}
+
+impl From<ping::Error> for PingError {
+    fn from(e: ping::Error) -> Self {
+        let kind = e
+            .source()
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .map(io::Error::kind);
+
+        match kind {
+            Some(io::ErrorKind::PermissionDenied) => PingError::PermissionDenied,
+            Some(io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => PingError::Timeout,
+            _ => PingError::Send(e),
+        }
+    }
+}
+
+impl fmt::Display for PingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PingError::PermissionDenied => {
+                write!(f, "Raw socket permission denied — run elevated")
+            }
+            PingError::Timeout => write!(f, "Request timed out"),
+            PingError::Send(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PingError {}
+
+/// Latest result of a ping thread, shared with the UI that draws it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CurrentPing {
+    Waiting,
+    Rtt(Duration),
+    Failed(String),
+    PermissionDenied,
+}
+
+/// How an RTT is rounded for display. Measurements are kept as a full-precision
+/// [`Duration`] and only formatted through this at draw time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RttUnit {
+    #[default]
+    Millis,
+    MillisPrecise,
+    Micros,
+}
+
+impl RttUnit {
+    pub fn next(self) -> Self {
+        match self {
+            RttUnit::Millis => RttUnit::MillisPrecise,
+            RttUnit::MillisPrecise => RttUnit::Micros,
+            RttUnit::Micros => RttUnit::Millis,
+        }
+    }
+
+    /// Short name shown next to the key hint in the UI.
+    pub fn label(self) -> &'static str {
+        match self {
+            RttUnit::Millis => "ms",
+            RttUnit::MillisPrecise => "0.1 ms",
+            RttUnit::Micros => "µs",
+        }
+    }
+
+    pub fn format(self, rtt: Duration) -> String {
+        match self {
+            RttUnit::Millis => format!("{} ms", rtt.as_millis()),
+            RttUnit::MillisPrecise => format!("{:.1} ms", rtt.as_secs_f64() * 1000.0),
+            RttUnit::Micros => format!("{} µs", rtt.as_micros()),
+        }
+    }
+}
+
+/// Sends a single echo request to `target` and returns the round-trip time.
+///
+/// IPv6 targets are passed straight through to the ping crate, which chooses
+/// ICMP or ICMPv6 from the address family. The ICMPv6 path, including how the
+/// crate applies `ttl` as a hop limit on an IPv6 socket, has not been verified.
+///
+/// The ping crate does not report the reply timestamp, so the RTT is measured
+/// around `send()`. That includes opening the socket and encoding/decoding the
+/// packet (typically well under a millisecond) on top of the network round trip.
+/// Only `send()` itself is timed, and every app goes through this function, so
+/// they all report the same value.
+pub fn ping_once(target: IpAddr, timeout: Duration, ttl: u8) -> Result<Duration, PingError> {
+    let mut p = ping::new(target);
+    p.timeout(timeout).ttl(u32::from(ttl));
+
+    let start = Instant::now();
+    p.send()?;
+    Ok(start.elapsed())
+}
+
+/// Parses `host` as an IPv4 or IPv6 literal (optionally in `[...]` brackets),
+/// falling back to a DNS lookup for hostnames.
+///
+/// When a hostname resolves to both families, the first IPv4 address is used,
+/// since IPv4 connectivity is the more common case; IPv6 is used only if the
+/// name has no IPv4 address. Pass an IPv6 literal to ping over IPv6 explicitly.
+pub fn resolve_target(host: &str) -> Result<IpAddr, String> {
+    if let Some(literal) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return literal
+            .parse()
+            .map_err(|_| format!("{} is not a valid IPv6 address", literal));
+    }
+    if let Ok(ip) = host.parse() {
+        return Ok(ip);
+    }
+
+    let addrs: Vec<IpAddr> = (host, 0)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .map(|addr| addr.ip())
+        .collect();
+
+    addrs
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or(addrs.first())
+        .copied()
+        .ok_or_else(|| format!("no addresses found for {}", host))
+}
+
+/// Reads the optional target host from the first command-line argument,
+/// defaulting to [`DEFAULT_TARGET`]. If it can't be resolved, the returned
+/// error includes a usage line for the caller to show.
+pub fn target_from_args() -> Result<IpAddr, String> {
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_else(|| "ping".to_string());
+
+    match args.next() {
+        Some(host) => resolve_target(&host).map_err(|e| {
+            format!(
+                "Could not resolve {}: {}\nUsage: {} [host or IP]",
+                host, e, program
+            )
+        }),
+        None => Ok(DEFAULT_TARGET),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    const GOOGLE_V6: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888));
+
+    #[test]
+    fn rtt_unit_next_cycles_through_all_units() {
+        assert_eq!(RttUnit::Millis.next(), RttUnit::MillisPrecise);
+        assert_eq!(RttUnit::MillisPrecise.next(), RttUnit::Micros);
+        assert_eq!(RttUnit::Micros.next(), RttUnit::Millis);
+    }
+
+    #[test]
+    fn rtt_unit_format_rounds_only_for_display() {
+        let rtt = Duration::from_micros(12_345);
+        assert_eq!(RttUnit::Millis.format(rtt), "12 ms");
+        assert_eq!(RttUnit::MillisPrecise.format(rtt), "12.3 ms");
+        assert_eq!(RttUnit::Micros.format(rtt), "12345 µs");
+    }
+
+    #[test]
+    fn rtt_unit_format_handles_sub_millisecond_values() {
+        let rtt = Duration::from_micros(450);
+        assert_eq!(RttUnit::Millis.format(rtt), "0 ms");
+        assert_eq!(RttUnit::MillisPrecise.format(rtt), "0.5 ms");
+        assert_eq!(RttUnit::Micros.format(rtt), "450 µs");
+    }
+
+    fn ping_io_error(kind: io::ErrorKind) -> PingError {
+        PingError::from(ping::Error::IoError {
+            error: io::Error::from(kind),
+        })
+    }
+
+    #[test]
+    fn ping_error_classifies_permission_denied() {
+        assert!(matches!(
+            ping_io_error(io::ErrorKind::PermissionDenied),
+            PingError::PermissionDenied
+        ));
+    }
+
+    #[test]
+    fn ping_error_classifies_timeouts() {
+        assert!(matches!(
+            ping_io_error(io::ErrorKind::WouldBlock),
+            PingError::Timeout
+        ));
+        assert!(matches!(
+            ping_io_error(io::ErrorKind::TimedOut),
+            PingError::Timeout
+        ));
+    }
+
+    #[test]
+    fn ping_error_passes_other_errors_through() {
+        assert!(matches!(
+            ping_io_error(io::ErrorKind::ConnectionRefused),
+            PingError::Send(_)
+        ));
+        assert!(matches!(
+            PingError::from(ping::Error::InternalError),
+            PingError::Send(_)
+        ));
+    }
+
+    #[test]
+    fn resolve_target_parses_ipv6_literal() {
+        assert_eq!(resolve_target("2001:4860:4860::8888"), Ok(GOOGLE_V6));
+    }
+
+    #[test]
+    fn resolve_target_strips_ipv6_brackets() {
+        assert_eq!(resolve_target("[2001:4860:4860::8888]"), Ok(GOOGLE_V6));
+    }
+
+    #[test]
+    fn resolve_target_parses_ipv4_literal() {
+        assert_eq!(resolve_target("8.8.8.8"), Ok(DEFAULT_TARGET));
+    }
+
+    #[test]
+    fn resolve_target_rejects_bracketed_non_address() {
+        assert!(resolve_target("[foo]").is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use sdl2::image::LoadTexture;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+
+pub const FONT_PATH: &str = "assets/Roboto-Medium.ttf";
+
+const BUNDLED_FONT: &[u8] = include_bytes!("../assets/Roboto-Medium.ttf");
+
+/// Loads the UI font from [`FONT_PATH`], falling back to the copy embedded in
+/// this crate if the file is missing or unreadable. When the fallback is used,
+/// the reason is returned alongside the font for the caller to report.
+pub fn load_font(
+    ttf_context: &Sdl2TtfContext,
+    size: u16,
+) -> Result<(Font<'_, 'static>, Option<String>), String> {
+    match ttf_context.load_font(FONT_PATH, size) {
+        Ok(font) => Ok((font, None)),
+        Err(e) => {
+            let font = ttf_context.load_font_from_rwops(RWops::from_bytes(BUNDLED_FONT)?, size)?;
+            let warning = format!("Failed to load {}: {}, using bundled font", FONT_PATH, e);
+            Ok((font, Some(warning)))
+        }
+    }
+}
+
+/// Loads a background image. The background is optional, so callers usually
+/// report the error and draw a solid color instead.
+pub fn load_background<'a>(
+    texture_creator: &'a TextureCreator<WindowContext>,
+    path: &str,
+) -> Result<Texture<'a>, String> {
+    texture_creator
+        .load_texture(path)
+        .map_err(|e| format!("Failed to load {}: {}", path, e))
+}